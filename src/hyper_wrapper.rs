@@ -5,26 +5,85 @@ use opentelemetry::{
     KeyValue,
 };
 
+/// Controls which OpenTelemetry semantic-convention attribute names are emitted for HTTP spans.
+///
+/// The HTTP trace semantic conventions were stabilized after rs-consul originally adopted the
+/// experimental `http.*` attributes (e.g. `http.method`, `http.url`). This enum lets callers pick
+/// which generation of attribute names to emit so they can migrate collectors/dashboards at their
+/// own pace.
+/// See https://opentelemetry.io/docs/specs/semconv/http/http-spans/
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SemConvStability {
+    /// Emit only the old, experimental attribute names (`http.method`, `http.url`, ...).
+    Old,
+    /// Emit only the new, stable attribute names (`http.request.method`, `url.full`, ...).
+    New,
+    /// Emit both the old and new attribute names, so neither old nor new dashboards break.
+    Dup,
+}
+
+impl Default for SemConvStability {
+    /// Defaults to [`SemConvStability::Dup`] so existing dashboards keep working while new ones
+    /// can be built against the stable attribute names.
+    fn default() -> Self {
+        SemConvStability::Dup
+    }
+}
+
 /// Create an OpenTelemetry Span for the given HTTP request, according to the OpenTelemetry
 /// semantic conventions for HTTP traffic.
 /// See https://github.com/open-telemetry/opentelemetry-specification/blob/v0.5.0/specification/trace/semantic_conventions/http.md
-pub fn span_for_request<T>(tracer: &BoxedTracer, req: &hyper::Request<T>) -> BoxedSpan {
+/// See https://opentelemetry.io/docs/specs/semconv/http/http-spans/
+pub fn span_for_request<T>(
+    tracer: &BoxedTracer,
+    req: &hyper::Request<T>,
+    stability: SemConvStability,
+) -> BoxedSpan {
     let span = tracer.start(&format!(
         "HTTP {} {}",
         req.method(),
         req.uri().host().unwrap_or("<unknown>")
     ));
     span.set_attribute(KeyValue::new("span.kind", "client"));
-    span.set_attribute(KeyValue::new("http.method", req.method().to_string()));
-    span.set_attribute(KeyValue::new("http.url", req.uri().to_string()));
-    if let Some(path_and_query) = req.uri().path_and_query() {
-        span.set_attribute(KeyValue::new("http.target", path_and_query.to_string()));
-    }
-    if let Some(host) = req.uri().host() {
-        span.set_attribute(KeyValue::new("http.host", host.to_owned()));
+
+    let emit_old = matches!(stability, SemConvStability::Old | SemConvStability::Dup);
+    let emit_new = matches!(stability, SemConvStability::New | SemConvStability::Dup);
+
+    if emit_old {
+        span.set_attribute(KeyValue::new("http.method", req.method().to_string()));
+        span.set_attribute(KeyValue::new("http.url", req.uri().to_string()));
+        if let Some(path_and_query) = req.uri().path_and_query() {
+            span.set_attribute(KeyValue::new("http.target", path_and_query.to_string()));
+        }
+        if let Some(host) = req.uri().host() {
+            span.set_attribute(KeyValue::new("http.host", host.to_owned()));
+        }
+        if let Some(scheme) = req.uri().scheme_str() {
+            span.set_attribute(KeyValue::new("http.scheme", scheme.to_string()));
+        }
     }
-    if let Some(scheme) = req.uri().scheme_str() {
-        span.set_attribute(KeyValue::new("http.scheme", scheme.to_string()));
+
+    if emit_new {
+        span.set_attribute(KeyValue::new(
+            "http.request.method",
+            req.method().to_string(),
+        ));
+        span.set_attribute(KeyValue::new("url.full", req.uri().to_string()));
+        if let Some(path_and_query) = req.uri().path_and_query() {
+            span.set_attribute(KeyValue::new("url.path", path_and_query.path().to_owned()));
+            if let Some(query) = path_and_query.query() {
+                span.set_attribute(KeyValue::new("url.query", query.to_owned()));
+            }
+        }
+        if let Some(scheme) = req.uri().scheme_str() {
+            span.set_attribute(KeyValue::new("url.scheme", scheme.to_string()));
+        }
+        if let Some(host) = req.uri().host() {
+            span.set_attribute(KeyValue::new("server.address", host.to_owned()));
+        }
+        if let Some(port) = req.uri().port_u16() {
+            span.set_attribute(KeyValue::new("server.port", port as i64));
+        }
     }
 
     // Using strings from https://github.com/open-telemetry/opentelemetry-specification/blob/v0.5.0/specification/trace/semantic_conventions/http.md#common-attributes
@@ -35,7 +94,12 @@ pub fn span_for_request<T>(tracer: &BoxedTracer, req: &hyper::Request<T>) -> Box
         Version::HTTP_3 => "3",
         _ => "unknown",
     };
-    span.set_attribute(KeyValue::new("http.flavor", serialized_version));
+    if emit_old {
+        span.set_attribute(KeyValue::new("http.flavor", serialized_version));
+    }
+    if emit_new {
+        span.set_attribute(KeyValue::new("network.protocol.version", serialized_version));
+    }
 
     // TODO: Emit UserAgent
     // TODO: Expose non-HTTP specific attributes https://github.com/open-telemetry/opentelemetry-specification/blob/v0.5.0/specification/trace/semantic_conventions/span-general.md#general-network-connection-attributes
@@ -45,21 +109,34 @@ pub fn span_for_request<T>(tracer: &BoxedTracer, req: &hyper::Request<T>) -> Box
 
 /// Annotate a span that has previously been created given the HTTP response.
 /// The passed in span must have been created for the HTTP request for which we got the response.
-pub fn annotate_span_for_response<T>(span: &BoxedSpan, response: &hyper::Response<T>) {
+pub fn annotate_span_for_response<T>(
+    span: &BoxedSpan,
+    response: &hyper::Response<T>,
+    stability: SemConvStability,
+) {
     let status = response.status();
 
-    span.set_attribute(KeyValue::new(
-        "http.status_code",
-        status.as_u16().to_string(),
-    ));
-    if let Some(canonical_reason) = status.canonical_reason() {
+    if matches!(stability, SemConvStability::Old | SemConvStability::Dup) {
         span.set_attribute(KeyValue::new(
-            "http.status_text",
-            canonical_reason.to_owned(),
+            "http.status_code",
+            status.as_u16().to_string(),
+        ));
+        if let Some(canonical_reason) = status.canonical_reason() {
+            span.set_attribute(KeyValue::new(
+                "http.status_text",
+                canonical_reason.to_owned(),
+            ));
+        }
+    }
+
+    if matches!(stability, SemConvStability::New | SemConvStability::Dup) {
+        span.set_attribute(KeyValue::new(
+            "http.response.status_code",
+            status.as_u16() as i64,
         ));
     }
 
     if status != hyper::StatusCode::OK {
         span.set_status(StatusCode::Error, status.as_str().to_owned());
     }
-}
\ No newline at end of file
+}